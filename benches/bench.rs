@@ -1,15 +1,33 @@
+#[cfg(feature = "reqwest")]
+use criterion::BatchSize;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use tiny_dynamo::{Const, Credentials, Request, Table, DB};
 
 fn get_item(db: DB) -> Result<Request, Box<dyn std::error::Error>> {
-    db.get_item_req("test")
+    Ok(db.get_item_req("test")?)
 }
 
 fn put_item(db: DB) -> Result<Request, Box<dyn std::error::Error>> {
-    db.put_item_req("test", "value")
+    Ok(db.put_item_req("test", "value")?)
 }
 
 fn criterion_benchmark(c: &mut Criterion) {
+    // signing keys are derived once and reused by a long lived DB
+    let db = DB::new(
+        Credentials::new("test", "test"),
+        Table::new(
+            "test",
+            "key",
+            "value",
+            "us-east-1".parse().expect("valid region"),
+            Some("http://localhost:8000".into()),
+        ),
+        Const(200, "".into()),
+    );
+    c.bench_function("get_item_reused_db", |b| {
+        b.iter(|| black_box(&db).get_item_req("test"))
+    });
+
     c.bench_function("get_item", |b| {
         b.iter(|| {
             get_item(black_box(DB::new(
@@ -43,5 +61,57 @@ fn criterion_benchmark(c: &mut Criterion) {
     });
 }
 
+/// Compares building a reqwest request from a signed request carrying a large value by cloning its headers
+/// and body, as `Reqwest` once did, with moving them out of the owned request, as it does now
+#[cfg(feature = "reqwest")]
+fn reqwest_benchmark(c: &mut Criterion) {
+    let client = reqwest::blocking::Client::new();
+    let db = DB::new(
+        Credentials::new("test", "test"),
+        Table::new(
+            "test",
+            "key",
+            "value",
+            "us-east-1".parse().expect("valid region"),
+            Some("http://localhost:8000".into()),
+        ),
+        Const(200, "".into()),
+    );
+    let value = "x".repeat(350 * 1024);
+    let signed = || db.put_item_req("test", &value).expect("signed request");
+
+    c.bench_function("reqwest_request_cloned_parts", |b| {
+        b.iter_batched(
+            signed,
+            |signed| {
+                client
+                    .post(signed.uri().to_string())
+                    .headers(signed.headers().clone())
+                    .body(signed.body().clone())
+                    .build()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("reqwest_request_moved_parts", |b| {
+        b.iter_batched(
+            signed,
+            |signed| {
+                let (parts, body) = signed.into_parts();
+                client
+                    .post(parts.uri.to_string())
+                    .headers(parts.headers)
+                    .body(body)
+                    .build()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+#[cfg(feature = "reqwest")]
+criterion_group!(benches, criterion_benchmark, reqwest_benchmark);
+#[cfg(not(feature = "reqwest"))]
 criterion_group!(benches, criterion_benchmark);
 criterion_main!(benches);
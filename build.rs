@@ -1,5 +1,16 @@
 use std::{env, error::Error, fs, path::Path};
 
+/// Regions providing FIPS 140-2 validated DynamoDB endpoints
+const FIPS: [&str; 7] = [
+    "ca-central-1",
+    "us-east-1",
+    "us-east-2",
+    "us-gov-east-1",
+    "us-gov-west-1",
+    "us-west-1",
+    "us-west-2",
+];
+
 struct Region {
     variant: String,
     id: String,
@@ -32,18 +43,30 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     // the enum
     let mut buf =
-        "/// A list of AWS Regions supported by DynamoDB\n#[non_exhaustive]\npub enum Region {\n"
+        "/// A list of AWS Regions supported by DynamoDB\n#[non_exhaustive]\n#[derive(Clone)]\npub enum Region {\n"
             .to_string();
     for region in &regions {
         buf.push_str("  ");
         buf.push_str(&region.variant);
         buf.push_str(",\n");
     }
+    buf.push_str("  /// A region not listed above, i.e. one launched after this crate was released, addressed at a given endpoint. See `Region::custom`\n");
+    buf.push_str("  Custom {\n");
+    buf.push_str("    /// Short region identifier\n");
+    buf.push_str("    id: String,\n");
+    buf.push_str("    /// DynamoDB endpoint uri\n");
+    buf.push_str("    endpoint: String,\n");
+    buf.push_str("  },\n");
     buf.push_str("}\n");
 
     // the impl
     buf.push_str("\nimpl Region {\n");
 
+    buf.push_str("  /// Returns a region not otherwise listed, addressed at a given endpoint\n");
+    buf.push_str("  pub fn custom(id: impl AsRef<str>, endpoint: impl AsRef<str>) -> Self {\n");
+    buf.push_str("    Region::Custom { id: id.as_ref().to_owned(), endpoint: endpoint.as_ref().to_owned() }\n");
+    buf.push_str("  }\n");
+
     buf.push_str("  /// Short region identifier\n");
     buf.push_str("  pub fn id(&self) -> &str {\n");
     buf.push_str("    match self {\n");
@@ -54,6 +77,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         buf.push_str(&region.id);
         buf.push_str("\",\n");
     }
+    buf.push_str("      Region::Custom { id, .. } => id,\n");
     buf.push_str("    }\n  }\n");
 
     buf.push_str("  /// region specific dynamodb endpoint\n");
@@ -66,12 +90,85 @@ fn main() -> Result<(), Box<dyn Error>> {
         buf.push_str(&region.id);
         buf.push_str(".amazonaws.com\",\n");
     }
+    buf.push_str("      Region::Custom { endpoint, .. } => endpoint,\n");
+    buf.push_str("    }\n  }\n");
+
+    buf.push_str("  /// region specific FIPS 140-2 validated dynamodb endpoint, for regions which provide one\n");
+    buf.push_str("  pub fn fips_endpoint(&self) -> Option<&str> {\n");
+    buf.push_str("    match self {\n");
+    for region in &regions {
+        if !FIPS.contains(&region.id.as_str()) {
+            continue;
+        }
+        buf.push_str("      Region::");
+        buf.push_str(&region.variant);
+        // GovCloud endpoints are FIPS validated without a distinct host
+        if region.id.starts_with("us-gov-") {
+            buf.push_str(" => Some(\"https://dynamodb.");
+        } else {
+            buf.push_str(" => Some(\"https://dynamodb-fips.");
+        }
+        buf.push_str(&region.id);
+        buf.push_str(".amazonaws.com\"),\n");
+    }
+    buf.push_str("      _ => None,\n");
+    buf.push_str("    }\n  }\n");
+
+    buf.push_str("  /// region specific dualstack dynamodb endpoint, reachable over both IPv4 and IPv6, for listed regions\n");
+    buf.push_str("  pub fn dualstack_endpoint(&self) -> Option<&str> {\n");
+    buf.push_str("    match self {\n");
+    for region in &regions {
+        buf.push_str("      Region::");
+        buf.push_str(&region.variant);
+        buf.push_str(" => Some(\"https://dynamodb.");
+        buf.push_str(&region.id);
+        buf.push_str(".api.aws\"),\n");
+    }
+    buf.push_str("      Region::Custom { .. } => None,\n");
+    buf.push_str("    }\n  }\n");
+
+    buf.push_str("  /// region specific FIPS 140-2 validated dualstack dynamodb endpoint, for regions which provide one\n");
+    buf.push_str("  pub fn fips_dualstack_endpoint(&self) -> Option<&str> {\n");
+    buf.push_str("    match self {\n");
+    for region in &regions {
+        if !FIPS.contains(&region.id.as_str()) {
+            continue;
+        }
+        buf.push_str("      Region::");
+        buf.push_str(&region.variant);
+        buf.push_str(" => Some(\"https://dynamodb-fips.");
+        buf.push_str(&region.id);
+        buf.push_str(".api.aws\"),\n");
+    }
+    buf.push_str("      _ => None,\n");
     buf.push_str("    }\n  }\n");
+
+    buf.push_str("  /// All listed regions, in the order of their short region identifiers\n");
+    buf.push_str("  pub fn all() -> &'static [Region] {\n");
+    buf.push_str("    &[\n");
+    for region in &regions {
+        buf.push_str("      Region::");
+        buf.push_str(&region.variant);
+        buf.push_str(",\n");
+    }
+    buf.push_str("    ]\n  }\n");
+
+    buf.push_str(
+        "  /// Short region identifiers of all regions, in the same order as `Region::all`\n",
+    );
+    buf.push_str("  pub fn variants() -> &'static [&'static str] {\n");
+    buf.push_str("    &[\n");
+    for region in &regions {
+        buf.push_str("      \"");
+        buf.push_str(&region.id);
+        buf.push_str("\",\n");
+    }
+    buf.push_str("    ]\n  }\n");
     buf.push_str("}\n");
 
     // from str
     buf.push_str("\nimpl std::str::FromStr for Region {\n");
-    buf.push_str("  type Err = String;\n");
+    buf.push_str("  type Err = ParseRegionError;\n");
 
     buf.push_str("  fn from_str(s: &str) ->  Result<Self, Self::Err> {\n");
     buf.push_str("    match s {\n");
@@ -82,7 +179,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         buf.push_str(&region.variant);
         buf.push_str("),\n");
     }
-    buf.push_str("      _ => Err(\"invalid region\".into()),\n");
+    buf.push_str("      _ => Err(ParseRegionError(s.to_owned())),\n");
     buf.push_str("    }\n  }\n");
     buf.push_str("}\n");
 
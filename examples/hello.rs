@@ -6,10 +6,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     // AWS_ACCESS_KEY_ID=foo AWS_SECRET_ACCESS_KEY=foo aws dynamodb create-table --endpoint-url http://localhost:8000 --table-name test --key-schema AttributeName=key,KeyType=HASH --attribute-definitions AttributeName=key,AttributeType=S --provisioned-throughput ReadCapacityUnits=1,WriteCapacityUnits=1
     // https://www.rahulpnath.com/blog/aws_dynamodb_local/
     let db = DB::new(
-        Credentials::new(
-            env::var("AWS_ACCESS_KEY_ID")?,
-            env::var("AWS_SECRET_ACCESS_KEY")?,
-        ),
+        Credentials::from_env()?,
         Table::new(
             env::var("TABLE_NAME").ok().as_deref().unwrap_or("test"),
             env::var("KEY_NAME").ok().as_deref().unwrap_or("key"),
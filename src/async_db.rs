@@ -0,0 +1,287 @@
+use crate::{
+    Attr, ConsumedCapacity, CredentialsProvider, Error, GetItemInput, Protocol, PutItemInput,
+    Request, Table,
+};
+use futures_util::{
+    future::BoxFuture,
+    stream::{self, StreamExt, TryStreamExt},
+};
+use std::error::Error as StdError;
+
+/// The number of pipelined operations in flight at any one time, unless otherwise configured
+const DEFAULT_CONCURRENCY: usize = 10;
+
+/// An async analog of `Transport`
+///
+/// Implementations return a boxed future so that the trait may be used as a trait object. Futures are `Send`
+/// so that those of an `AsyncDB` may be spawned on multi-threaded executors, i.e. `tokio::spawn`
+pub trait AsyncTransport {
+    /// Accepts a signed `http::Request<Vec<u8>>` and resolves a tuple
+    /// representing a response's HTTP status code and body
+    fn send(
+        &self,
+        signed: Request,
+    ) -> BoxFuture<'_, Result<(u16, String), Box<dyn StdError + Send + Sync>>>;
+}
+
+/// An async analog of `DB`
+pub struct AsyncDB {
+    protocol: Protocol,
+    transport: Box<dyn AsyncTransport + Send + Sync>,
+}
+
+impl AsyncDB {
+    /// Returns a new instance of an AsyncDB
+    pub fn new(
+        credentials: impl CredentialsProvider + Send + Sync + 'static,
+        table_info: Table,
+        transport: impl AsyncTransport + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            protocol: Protocol::new(credentials, table_info),
+            transport: Box::new(transport),
+        }
+    }
+
+    /// Gets a value by its key
+    pub async fn get(
+        &self,
+        key: impl AsRef<str>,
+    ) -> Result<Option<String>, Error> {
+        let (value, _) = self
+            .get_item(self.protocol.get_item_input(key.as_ref()))
+            .await?;
+        self.protocol.string_value(value)
+    }
+
+    /// Sets a value for a given key
+    pub async fn set(
+        &self,
+        key: impl AsRef<str>,
+        value: impl AsRef<str>,
+    ) -> Result<(), Error> {
+        self.put_item(self.protocol.put_item_input(key.as_ref(), value.as_ref()))
+            .await
+            .map(|_| ())
+    }
+
+    /// Returns a new `Pipeline` for queueing up a mix of gets and sets to be sent concurrently
+    ///
+    /// # Example
+    ///
+    /// ```rust ,no_run
+    /// # use tiny_dynamo::AsyncDB;
+    /// # async fn run(db: AsyncDB) -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut pipeline = db.pipeline();
+    /// let foo = pipeline.get("foo");
+    /// pipeline.set("bar", "baz");
+    /// let results = pipeline.send().await?;
+    /// println!("{:?}", results.get(foo));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pipeline(&self) -> Pipeline<'_> {
+        Pipeline {
+            db: self,
+            ops: Vec::new(),
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    async fn get_item(
+        &self,
+        input: GetItemInput<'_>,
+    ) -> Result<(Option<Attr>, Option<ConsumedCapacity>), Error> {
+        let (status, body) = self
+            .transport
+            .send(self.protocol.request("GetItem", &input)?)
+            .await
+            .map_err(Error::transport)?;
+        self.protocol.decode_get_item(status, body)
+    }
+
+    async fn put_item(
+        &self,
+        input: PutItemInput<'_>,
+    ) -> Result<String, Error> {
+        let (status, body) = self
+            .transport
+            .send(self.protocol.request("PutItem", &input)?)
+            .await
+            .map_err(Error::transport)?;
+        self.protocol.decode_put_item(status, body)
+    }
+}
+
+enum Op {
+    Get(String),
+    Set(String, String),
+}
+
+/// A handle to the result of a get queued on a `Pipeline`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingGet(usize);
+
+/// A batch of gets and sets dispatched concurrently with a single `await`
+///
+/// Operations are independent of one another. No ordering is guaranteed between a set and
+/// a get of the same key queued on the same pipeline.
+pub struct Pipeline<'a> {
+    db: &'a AsyncDB,
+    ops: Vec<Op>,
+    concurrency: usize,
+}
+
+impl<'a> Pipeline<'a> {
+    /// Queues a get, returning a handle for retrieving its value from `PipelineResults`
+    pub fn get(
+        &mut self,
+        key: impl AsRef<str>,
+    ) -> PendingGet {
+        self.ops.push(Op::Get(key.as_ref().to_owned()));
+        PendingGet(self.ops.len() - 1)
+    }
+
+    /// Queues a set
+    pub fn set(
+        &mut self,
+        key: impl AsRef<str>,
+        value: impl AsRef<str>,
+    ) -> &mut Self {
+        self.ops
+            .push(Op::Set(key.as_ref().to_owned(), value.as_ref().to_owned()));
+        self
+    }
+
+    /// Limits the number of operations in flight at any one time. Defaults to 10
+    pub fn concurrency(
+        &mut self,
+        limit: usize,
+    ) -> &mut Self {
+        self.concurrency = limit.max(1);
+        self
+    }
+
+    /// Sends all queued operations, failing with the first error encountered
+    pub async fn send(self) -> Result<PipelineResults, Error> {
+        let Pipeline {
+            db,
+            ops,
+            concurrency,
+        } = self;
+        let values = stream::iter(ops.into_iter().map(|op| async move {
+            match op {
+                Op::Get(key) => db.get(key).await,
+                Op::Set(key, value) => db.set(key, value).await.map(|_| None),
+            }
+        }))
+        .buffered(concurrency)
+        .try_collect()
+        .await?;
+        Ok(PipelineResults { values })
+    }
+}
+
+/// The results of a sent `Pipeline`
+#[derive(Debug)]
+pub struct PipelineResults {
+    values: Vec<Option<String>>,
+}
+
+impl PipelineResults {
+    /// Returns the value resolved for a queued get, if one was found
+    pub fn get(
+        &self,
+        pending: PendingGet,
+    ) -> Option<&str> {
+        self.values
+            .get(pending.0)
+            .and_then(|value| value.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::test_table, Credentials};
+    use futures_util::FutureExt;
+    use std::{collections::HashMap, sync::Mutex};
+
+    /// Answers gets with the last value set for a key
+    #[derive(Default)]
+    struct Store(Mutex<HashMap<String, String>>);
+
+    impl AsyncTransport for Store {
+        fn send(
+            &self,
+            signed: Request,
+        ) -> BoxFuture<'_, Result<(u16, String), Box<dyn StdError + Send + Sync>>> {
+            async move {
+                let target = signed.headers()["X-Amz-Target"].to_str()?.to_owned();
+                let body: serde_json::Value = serde_json::from_slice(signed.body())?;
+                let mut store = self.0.lock().unwrap();
+                if target.ends_with("PutItem") {
+                    store.insert(
+                        body["Item"]["key"]["S"].as_str().unwrap_or_default().into(),
+                        body["Item"]["value"]["S"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .into(),
+                    );
+                    return Ok((200, "{}".into()));
+                }
+                Ok((
+                    200,
+                    store
+                        .get(body["Key"]["key"]["S"].as_str().unwrap_or_default())
+                        .map(|value| serde_json::json!({ "Item": { "value": { "S": value } } }))
+                        .unwrap_or_else(|| serde_json::json!({}))
+                        .to_string(),
+                ))
+            }
+            .boxed()
+        }
+    }
+
+    #[test]
+    fn futures_are_send() -> Result<(), Box<dyn StdError>> {
+        fn assert_send<T: Send>(_: &T) {}
+        let db = AsyncDB::new(
+            Credentials::new("test", "test"),
+            test_table(),
+            Store::default(),
+        );
+        assert_send(&db.get("foo"));
+        assert_send(&db.set("foo", "bar"));
+        assert_send(&db.pipeline().send());
+        Ok(())
+    }
+
+    #[test]
+    fn pipeline_resolves_gets_in_queued_order() -> Result<(), Box<dyn StdError>> {
+        let transport = Store::default();
+        transport
+            .0
+            .lock()
+            .unwrap()
+            .extend([("a".into(), "1".into()), ("c".into(), "3".into())]);
+        let db = AsyncDB::new(Credentials::new("test", "test"), test_table(), transport);
+        let mut pipeline = db.pipeline();
+        let a = pipeline.get("a");
+        let b = pipeline.get("b");
+        pipeline.set("d", "4");
+        let c = pipeline.get("c");
+        let results = pipeline
+            .send()
+            .now_or_never()
+            .expect("expected ready future")?;
+        assert_eq!(results.get(a), Some("1"));
+        assert_eq!(results.get(b), None);
+        assert_eq!(results.get(c), Some("3"));
+        assert_eq!(
+            db.get("d").now_or_never().expect("expected ready future")?,
+            Some("4".into())
+        );
+        Ok(())
+    }
+}
@@ -1,11 +1,15 @@
-use crate::{Request, Transport};
+use crate::{
+    transport::{read_limited, Limited},
+    Request, Transport,
+};
 use fastly::{Body, Request as FastlyRequest};
-use std::error::Error;
+use std::{error::Error, io::Read};
 
 /// Provides a `Transport` impl suitable for Fastly's Compute@Edge WASM
 /// runtime
 pub struct Fastly {
     backend: String,
+    max_response_bytes: Option<u64>,
 }
 
 impl Fastly {
@@ -13,19 +17,77 @@ impl Fastly {
     pub fn new(backend: impl AsRef<str>) -> Self {
         Self {
             backend: backend.as_ref().to_string(),
+            max_response_bytes: None,
         }
     }
+
+    /// Limits the size of response bodies, which are otherwise read in full whatever their size
+    ///
+    /// Responses whose body exceeds `max` bytes fail with a `transport::ResponseTooLarge` error as soon as the
+    /// limit is reached, without buffering the rest of the body. Compressed bodies are limited both by their
+    /// compressed and their decompressed size. Reads of a body returned by `send_streaming` fail with an `io::Error`
+    /// wrapping a `transport::ResponseTooLarge` once the limit is exceeded
+    pub fn with_max_response_bytes(
+        mut self,
+        max: u64,
+    ) -> Self {
+        self.max_response_bytes = Some(max);
+        self
+    }
 }
 
 impl Transport for Fastly {
     fn send(
         &self,
         signed: Request,
-    ) -> Result<(u16, String), Box<dyn Error>> {
+    ) -> Result<(u16, String), Box<dyn Error + Send + Sync>> {
+        let (parts, body) = signed.into_parts();
+        let fastly_body: Body = body.into();
+        let fr: FastlyRequest = http::Request::from_parts(parts, fastly_body).into();
+        let mut resp = fr.send(&self.backend)?;
+        let status = resp.get_status().as_u16();
+        #[cfg(feature = "gzip")]
+        let encoding = resp
+            .get_header_str(http::header::CONTENT_ENCODING)
+            .map(str::to_owned);
+        let bytes = match self.max_response_bytes {
+            Some(limit) => read_limited(resp.take_body(), limit)?,
+            None => resp.into_body_bytes(),
+        };
+        #[cfg(feature = "gzip")]
+        let body = match self.max_response_bytes {
+            Some(limit) => {
+                crate::transport::decode_body_limited(encoding.as_deref(), bytes, limit)?
+            }
+            None => crate::transport::decode_body(encoding.as_deref(), bytes)?,
+        };
+        #[cfg(not(feature = "gzip"))]
+        let body = String::from_utf8(bytes)?;
+        Ok((status, body))
+    }
+
+    fn send_streaming(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, Box<dyn Read + Send>), Box<dyn Error + Send + Sync>> {
         let (parts, body) = signed.into_parts();
         let fastly_body: Body = body.into();
         let fr: FastlyRequest = http::Request::from_parts(parts, fastly_body).into();
         let resp = fr.send(&self.backend)?;
-        Ok((resp.get_status().as_u16(), resp.into_body_str()))
+        let status = resp.get_status().as_u16();
+        #[cfg(feature = "gzip")]
+        let body = {
+            let encoding = resp
+                .get_header_str(http::header::CONTENT_ENCODING)
+                .map(str::to_owned);
+            crate::transport::decode_reader(encoding.as_deref(), resp.into_body())
+        };
+        #[cfg(not(feature = "gzip"))]
+        let body: Box<dyn Read + Send> = Box::new(resp.into_body());
+        let body = match self.max_response_bytes {
+            Some(limit) => Box::new(Limited::new(body, limit)),
+            None => body,
+        };
+        Ok((status, body))
     }
 }
@@ -0,0 +1,95 @@
+//! An `AsyncTransport` backed by [hyper](https://crates.io/crates/hyper), for tokio based applications
+//! which already depend on hyper and would rather not depend on reqwest
+use crate::{AsyncTransport, Request};
+use futures_util::future::{BoxFuture, FutureExt};
+use hyper_client::{
+    client::{connect::Connect, HttpConnector},
+    Body, Client,
+};
+use hyper_tls::HttpsConnector;
+use std::error::Error;
+
+/// Provides an `AsyncTransport` backed by a `hyper::Client`, for use with an `AsyncDB`
+///
+/// Requests must be sent from within a tokio runtime
+pub struct Hyper<C = HttpsConnector<HttpConnector>> {
+    client: Client<C>,
+}
+
+impl Default for Hyper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hyper {
+    /// Returns a new instance backed by a default `hyper::Client` connecting over https
+    pub fn new() -> Self {
+        Self::from_client(Client::builder().build(HttpsConnector::new()))
+    }
+}
+
+impl<C> Hyper<C> {
+    /// Returns a new instance backed by a `hyper::Client` configured by the caller, i.e. with a custom
+    /// connector or connection pool settings
+    pub fn from_client(client: Client<C>) -> Self {
+        Hyper { client }
+    }
+}
+
+impl<C> AsyncTransport for Hyper<C>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    fn send(
+        &self,
+        signed: Request,
+    ) -> BoxFuture<'_, Result<(u16, String), Box<dyn Error + Send + Sync>>> {
+        async move {
+            // hyper shares its request type with the http crate so only the body needs converting
+            let (parts, body) = signed.into_parts();
+            let resp = self
+                .client
+                .request(http::Request::from_parts(parts, Body::from(body)))
+                .await?;
+            let status = resp.status().as_u16();
+            #[cfg(feature = "gzip")]
+            let encoding = resp
+                .headers()
+                .get(http::header::CONTENT_ENCODING)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned);
+            let bytes = hyper_client::body::to_bytes(resp.into_body()).await?;
+            #[cfg(feature = "gzip")]
+            let body = crate::transport::decode_body(encoding.as_deref(), bytes.to_vec())?;
+            #[cfg(not(feature = "gzip"))]
+            let body = String::from_utf8(bytes.to_vec())?;
+            Ok((status, body))
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::tests::server::{response, serve};
+
+    #[test]
+    fn hyper_sends_requests() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let (port, server) = serve(vec![response("200 OK", "", b"{}")])?;
+        let request = http::Request::builder()
+            .method("POST")
+            .uri(format!("http://127.0.0.1:{}", port))
+            .body(br#"{"TableName":"test"}"#.to_vec())?;
+        let transport = Hyper::from_client(Client::new());
+        let response = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(transport.send(request))?;
+        assert_eq!(response, (200, "{}".into()));
+        let received = server.join().expect("server panicked")?;
+        assert_eq!(received[0].body, br#"{"TableName":"test"}"#);
+        Ok(())
+    }
+}
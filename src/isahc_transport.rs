@@ -0,0 +1,78 @@
+//! A `Transport` backed by [isahc](https://crates.io/crates/isahc), an http client built on libcurl
+use crate::{Request, Transport};
+use http::HeaderMap;
+use isahc::{HttpClient, ReadResponseExt};
+use std::error::Error;
+
+/// Provides a `Transport` which sends requests with libcurl, gaining its HTTP/2 support and
+/// handling of system proxy configuration
+pub struct Isahc {
+    client: HttpClient,
+}
+
+impl Isahc {
+    /// Returns a new instance backed by a default `HttpClient`
+    pub fn new() -> Result<Self, isahc::Error> {
+        Ok(Self::from_client(HttpClient::new()?))
+    }
+
+    /// Returns a new instance backed by an `HttpClient` configured by the caller
+    pub fn from_client(client: HttpClient) -> Self {
+        Isahc { client }
+    }
+}
+
+impl Transport for Isahc {
+    fn send(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, String), Box<dyn Error + Send + Sync>> {
+        self.send_full(signed)
+            .map(|(status, _, body)| (status, body))
+    }
+
+    fn send_full(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, HeaderMap, String), Box<dyn Error + Send + Sync>> {
+        let mut resp = self.client.send(signed)?;
+        let status = resp.status().as_u16();
+        let headers = resp.headers().clone();
+        #[cfg(feature = "gzip")]
+        let body = {
+            let encoding = headers
+                .get(http::header::CONTENT_ENCODING)
+                .and_then(|value| value.to_str().ok());
+            crate::transport::decode_body(encoding, resp.bytes()?)?
+        };
+        #[cfg(not(feature = "gzip"))]
+        let body = resp.text()?;
+        Ok((status, headers, body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::tests::server::{response, serve};
+
+    #[test]
+    fn isahc_sends_requests() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let (port, server) = serve(vec![response(
+            "400 Bad Request",
+            "x-amzn-RequestId: abc\r\n",
+            b"{}",
+        )])?;
+        let request = http::Request::builder()
+            .method("POST")
+            .uri(format!("http://127.0.0.1:{}", port))
+            .body(br#"{"TableName":"test"}"#.to_vec())?;
+        let (status, headers, body) = Isahc::new()?.send_full(request)?;
+        assert_eq!(status, 400);
+        assert_eq!(headers["x-amzn-RequestId"], "abc");
+        assert_eq!(body, "{}");
+        let received = server.join().expect("server panicked")?;
+        assert_eq!(received[0].body, br#"{"TableName":"test"}"#);
+        Ok(())
+    }
+}
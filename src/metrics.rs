@@ -0,0 +1,187 @@
+//! A minimal registry of request metrics rendered in the
+//! [Prometheus text exposition format](https://prometheus.io/docs/instrumenting/exposition_formats/)
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex, time::Duration};
+
+/// Upper bounds, in seconds, of request latency histogram buckets
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Default)]
+struct Histogram {
+    /// counts of observations falling within each of `BUCKETS`, non-cumulative
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(
+        &mut self,
+        seconds: f64,
+    ) {
+        if let Some(idx) = BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[idx] += 1;
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+#[derive(Default)]
+struct Metrics {
+    requests: BTreeMap<String, u64>,
+    errors: BTreeMap<(String, String), u64>,
+    retries: BTreeMap<String, u64>,
+    durations: BTreeMap<String, Histogram>,
+}
+
+/// Accumulates metrics for each DynamoDB operation
+#[derive(Default)]
+pub(crate) struct Registry(Mutex<Metrics>);
+
+impl Registry {
+    /// Records a request sent for an operation and how long it took to complete
+    pub(crate) fn request(
+        &self,
+        operation: &str,
+        elapsed: Duration,
+    ) {
+        let mut metrics = self.lock();
+        *metrics.requests.entry(operation.into()).or_default() += 1;
+        metrics
+            .durations
+            .entry(operation.into())
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Records a failed request for an operation by the type of its error
+    pub(crate) fn error(
+        &self,
+        operation: &str,
+        error_type: &str,
+    ) {
+        *self
+            .lock()
+            .errors
+            .entry((operation.into(), error_type.into()))
+            .or_default() += 1;
+    }
+
+    /// Records a request resent for an operation
+    pub(crate) fn retry(
+        &self,
+        operation: &str,
+    ) {
+        *self.lock().retries.entry(operation.into()).or_default() += 1;
+    }
+
+    /// Renders all recorded metrics
+    pub(crate) fn render(&self) -> String {
+        let metrics = self.lock();
+        let mut out = String::new();
+        out.push_str("# HELP tiny_dynamo_requests_total DynamoDB requests sent\n");
+        out.push_str("# TYPE tiny_dynamo_requests_total counter\n");
+        for (operation, count) in &metrics.requests {
+            let _ = writeln!(
+                out,
+                "tiny_dynamo_requests_total{{operation=\"{}\"}} {}",
+                operation, count
+            );
+        }
+        out.push_str("# HELP tiny_dynamo_errors_total DynamoDB requests which failed\n");
+        out.push_str("# TYPE tiny_dynamo_errors_total counter\n");
+        for ((operation, error_type), count) in &metrics.errors {
+            let _ = writeln!(
+                out,
+                "tiny_dynamo_errors_total{{operation=\"{}\",type=\"{}\"}} {}",
+                operation,
+                escape(error_type),
+                count
+            );
+        }
+        out.push_str("# HELP tiny_dynamo_retries_total DynamoDB requests resent\n");
+        out.push_str("# TYPE tiny_dynamo_retries_total counter\n");
+        for (operation, count) in &metrics.retries {
+            let _ = writeln!(
+                out,
+                "tiny_dynamo_retries_total{{operation=\"{}\"}} {}",
+                operation, count
+            );
+        }
+        out.push_str("# HELP tiny_dynamo_request_duration_seconds DynamoDB request latency\n");
+        out.push_str("# TYPE tiny_dynamo_request_duration_seconds histogram\n");
+        for (operation, histogram) in &metrics.durations {
+            let mut cumulative = 0;
+            for (bound, count) in BUCKETS.iter().zip(&histogram.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "tiny_dynamo_request_duration_seconds_bucket{{operation=\"{}\",le=\"{}\"}} {}",
+                    operation, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "tiny_dynamo_request_duration_seconds_bucket{{operation=\"{}\",le=\"+Inf\"}} {}",
+                operation, histogram.count
+            );
+            let _ = writeln!(
+                out,
+                "tiny_dynamo_request_duration_seconds_sum{{operation=\"{}\"}} {}",
+                operation, histogram.sum
+            );
+            let _ = writeln!(
+                out,
+                "tiny_dynamo_request_duration_seconds_count{{operation=\"{}\"}} {}",
+                operation, histogram.count
+            );
+        }
+        out
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Metrics> {
+        // metrics remain usable even if a panic occurred while recording them
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Escapes a label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_accumulates_buckets() {
+        let registry = Registry::default();
+        registry.request("GetItem", Duration::from_millis(3));
+        registry.request("GetItem", Duration::from_millis(40));
+        registry.request("GetItem", Duration::from_secs(20));
+        registry.error("GetItem", "ThrottlingException");
+        registry.retry("BatchWriteItem");
+        let text = registry.render();
+        for line in &[
+            "tiny_dynamo_requests_total{operation=\"GetItem\"} 3",
+            "tiny_dynamo_errors_total{operation=\"GetItem\",type=\"ThrottlingException\"} 1",
+            "tiny_dynamo_retries_total{operation=\"BatchWriteItem\"} 1",
+            "tiny_dynamo_request_duration_seconds_bucket{operation=\"GetItem\",le=\"0.005\"} 1",
+            "tiny_dynamo_request_duration_seconds_bucket{operation=\"GetItem\",le=\"0.025\"} 1",
+            "tiny_dynamo_request_duration_seconds_bucket{operation=\"GetItem\",le=\"0.05\"} 2",
+            "tiny_dynamo_request_duration_seconds_bucket{operation=\"GetItem\",le=\"10\"} 2",
+            "tiny_dynamo_request_duration_seconds_bucket{operation=\"GetItem\",le=\"+Inf\"} 3",
+            "tiny_dynamo_request_duration_seconds_count{operation=\"GetItem\"} 3",
+        ] {
+            assert!(text.lines().any(|l| l == *line), "{}\n{}", line, text);
+        }
+    }
+}
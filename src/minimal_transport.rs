@@ -0,0 +1,487 @@
+//! A dependency minimal `Transport` implementing just enough HTTP/1.1 over `std::net`
+//! and [rustls](https://crates.io/crates/rustls) to talk to DynamoDB
+use crate::{Request, StrErr, Transport};
+use rustls::{ClientConfig, ClientSession, StreamOwned};
+use std::{
+    error::Error,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+/// Ports used when an endpoint does not specify one
+const HTTP_PORT: u16 = 80;
+const HTTPS_PORT: u16 = 443;
+
+/// How long reads and writes may block before failing when no timeout is configured
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+enum Stream {
+    Plain(TcpStream),
+    Tls(Box<StreamOwned<ClientSession, TcpStream>>),
+}
+
+impl Read for Stream {
+    fn read(
+        &mut self,
+        buf: &mut [u8],
+    ) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.read(buf),
+            Stream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(
+        &mut self,
+        buf: &[u8],
+    ) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.write(buf),
+            Stream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Plain(stream) => stream.flush(),
+            Stream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// An open connection along with the `scheme://authority` it is connected to
+struct Connection {
+    origin: String,
+    reader: BufReader<Stream>,
+}
+
+/// A response's status, the value of its `Content-Encoding` header and body
+struct Response {
+    status: u16,
+    #[cfg_attr(not(feature = "gzip"), allow(dead_code))]
+    content_encoding: Option<String>,
+    body: Vec<u8>,
+    keep_alive: bool,
+}
+
+/// Why exchanging a request and response over a connection failed
+enum ExchangeError {
+    /// The connection was closed before any of the request could have been processed, so the
+    /// request may safely be resent on a new connection
+    Stale(Box<dyn Error + Send + Sync>),
+    /// The request may have been processed so it must not be resent
+    Failed(Box<dyn Error + Send + Sync>),
+}
+
+impl From<ExchangeError> for Box<dyn Error + Send + Sync> {
+    fn from(err: ExchangeError) -> Self {
+        match err {
+            ExchangeError::Stale(err) | ExchangeError::Failed(err) => err,
+        }
+    }
+}
+
+/// Provides a `Transport` built on `std::net` and `rustls` alone, without a general purpose
+/// http client
+///
+/// Because every DynamoDB request is a `POST` to `/`, only the small subset of HTTP/1.1 needed
+/// for these requests is implemented. `https` endpoints are verified against the
+/// [Mozilla root certificates](https://crates.io/crates/webpki-roots). `http` endpoints, i.e.
+/// DynamoDB local, are also supported. A single connection is kept alive between requests.
+///
+/// Reads and writes fail once they block for longer than 30 seconds. See `Minimal::with_timeout`
+pub struct Minimal {
+    config: Arc<ClientConfig>,
+    connection: Mutex<Option<Connection>>,
+    timeout: Duration,
+}
+
+impl Default for Minimal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Minimal {
+    pub fn new() -> Self {
+        let mut config = ClientConfig::new();
+        config
+            .root_store
+            .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+        Minimal {
+            config: Arc::new(config),
+            connection: Mutex::new(None),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Configures how long each read or write of a connection may block before failing. Defaults to 30 seconds
+    pub fn with_timeout(
+        mut self,
+        timeout: Duration,
+    ) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn cached(&self) -> MutexGuard<'_, Option<Connection>> {
+        self.connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn connect(
+        &self,
+        scheme: &str,
+        host: &str,
+        port: u16,
+    ) -> Result<Stream, Box<dyn Error + Send + Sync>> {
+        let socket = TcpStream::connect((host, port))?;
+        socket.set_nodelay(true)?;
+        socket.set_read_timeout(Some(self.timeout))?;
+        socket.set_write_timeout(Some(self.timeout))?;
+        match scheme {
+            "https" => {
+                let name = webpki::DNSNameRef::try_from_ascii_str(host)
+                    .map_err(|_| StrErr(format!("invalid dns name {}", host)))?;
+                let session = ClientSession::new(&self.config, name);
+                Ok(Stream::Tls(Box::new(StreamOwned::new(session, socket))))
+            }
+            _ => Ok(Stream::Plain(socket)),
+        }
+    }
+}
+
+impl Transport for Minimal {
+    fn send(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, String), Box<dyn Error + Send + Sync>> {
+        let uri = signed.uri();
+        let scheme = uri.scheme_str().unwrap_or("https");
+        let host = uri
+            .host()
+            .ok_or_else(|| StrErr(format!("missing host in {}", uri)))?;
+        let port = uri.port_u16().unwrap_or(match scheme {
+            "http" => HTTP_PORT,
+            _ => HTTPS_PORT,
+        });
+        let origin = format!("{}://{}:{}", scheme, host, port);
+        let head = head(&signed);
+
+        // the connection is taken for the duration of the exchange so that concurrent requests
+        // open connections of their own rather than wait for it
+        let cached = self.cached().take().filter(|conn| conn.origin == origin);
+        let reused = match cached {
+            Some(mut conn) => match exchange(&mut conn.reader, &head, signed.body()) {
+                Ok(response) => Some((conn, response)),
+                // the server may have closed an idle connection so fall back on a new one
+                Err(ExchangeError::Stale(_)) => None,
+                // otherwise the request may already have been applied and resending it could apply it twice
+                Err(ExchangeError::Failed(err)) => return Err(err),
+            },
+            None => None,
+        };
+        let (conn, response) = match reused {
+            Some(exchanged) => exchanged,
+            None => {
+                let mut conn = Connection {
+                    origin,
+                    reader: BufReader::new(self.connect(scheme, host, port)?),
+                };
+                let response = exchange(&mut conn.reader, &head, signed.body())?;
+                (conn, response)
+            }
+        };
+        if response.keep_alive {
+            *self.cached() = Some(conn);
+        }
+        #[cfg(feature = "gzip")]
+        let body =
+            crate::transport::decode_body(response.content_encoding.as_deref(), response.body)?;
+        #[cfg(not(feature = "gzip"))]
+        let body = String::from_utf8(response.body)?;
+        Ok((response.status, body))
+    }
+}
+
+/// Returns the request line and headers of a request
+fn head(request: &Request) -> Vec<u8> {
+    let mut head = format!("{} / HTTP/1.1\r\n", request.method()).into_bytes();
+    for (name, value) in request.headers() {
+        head.extend_from_slice(name.as_str().as_bytes());
+        head.extend_from_slice(b": ");
+        head.extend_from_slice(value.as_bytes());
+        head.extend_from_slice(b"\r\n");
+    }
+    head.extend_from_slice(b"\r\n");
+    head
+}
+
+/// Writes a request and reads its response
+///
+/// Failures to write the request, or a connection closed before the first byte of a response, are
+/// reported as `ExchangeError::Stale`. All other failures are reported as `ExchangeError::Failed`
+fn exchange<S: Read + Write>(
+    reader: &mut BufReader<S>,
+    head: &[u8],
+    body: &[u8],
+) -> Result<Response, ExchangeError> {
+    let stream = reader.get_mut();
+    stream
+        .write_all(head)
+        .and_then(|_| stream.write_all(body))
+        .and_then(|_| stream.flush())
+        .map_err(|err| ExchangeError::Stale(Box::new(err)))?;
+    match reader.fill_buf() {
+        Ok([]) => {
+            return Err(ExchangeError::Stale(Box::new(StrErr(
+                "connection closed".into(),
+            ))))
+        }
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+            return Err(ExchangeError::Stale(Box::new(err)))
+        }
+        Err(err) => return Err(ExchangeError::Failed(Box::new(err))),
+        Ok(_) => (),
+    }
+    read_response(reader).map_err(ExchangeError::Failed)
+}
+
+fn read_response(reader: &mut impl BufRead) -> Result<Response, Box<dyn Error + Send + Sync>> {
+    let status_line = read_line(reader)?;
+    let mut parts = status_line.splitn(3, ' ');
+    let status = match (parts.next(), parts.next()) {
+        (Some(version), Some(status)) if version.starts_with("HTTP/1.") => status.parse()?,
+        _ => {
+            return Err(Box::new(StrErr(format!(
+                "invalid status line {}",
+                status_line
+            ))))
+        }
+    };
+    let mut content_length = None;
+    let mut content_encoding = None;
+    let mut chunked = false;
+    let mut keep_alive = !status_line.starts_with("HTTP/1.0");
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = match line.find(':') {
+            Some(idx) => (line[..idx].trim(), line[idx + 1..].trim()),
+            None => continue,
+        };
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = Some(value.parse::<usize>()?);
+        } else if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        } else if name.eq_ignore_ascii_case("content-encoding") {
+            content_encoding = Some(value.to_owned());
+        } else if name.eq_ignore_ascii_case("connection") {
+            keep_alive = !value.eq_ignore_ascii_case("close");
+        }
+    }
+    let body = if chunked {
+        read_chunked(reader)?
+    } else if let Some(len) = content_length {
+        let mut body = vec![0; len];
+        reader.read_exact(&mut body)?;
+        body
+    } else {
+        // without a length the body is delimited by the connection closing
+        keep_alive = false;
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body)?;
+        body
+    };
+    Ok(Response {
+        status,
+        content_encoding,
+        body,
+        keep_alive,
+    })
+}
+
+fn read_chunked(reader: &mut impl BufRead) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let mut body = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)?;
+        if size == 0 {
+            // skip any trailers
+            while !read_line(reader)?.is_empty() {}
+            return Ok(body);
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        read_line(reader)?;
+    }
+}
+
+/// Reads a single CRLF terminated line, without its terminator
+fn read_line(reader: &mut impl BufRead) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(Box::new(StrErr("connection closed".into())));
+    }
+    Ok(line.trim_end_matches(&['\r', '\n'][..]).to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::tests::server::{read_request, serve};
+    use std::{net::TcpListener, sync::mpsc, thread};
+
+    /// Returns a request for a local server listening on `port`
+    fn request(port: u16) -> Result<Request, http::Error> {
+        http::Request::builder()
+            .method("POST")
+            .uri(format!("http://127.0.0.1:{}", port))
+            .header("Host", format!("127.0.0.1:{}", port))
+            .header("Content-Length", "2")
+            .body(b"{}".to_vec())
+    }
+
+    #[test]
+    fn read_response_reads_content_length_bodies() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut raw = "HTTP/1.1 200 OK\r\nContent-Type: application/x-amz-json-1.0\r\nContent-Length: 2\r\n\r\n{}extra".as_bytes();
+        let response = read_response(&mut raw)?;
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"{}");
+        assert!(response.keep_alive);
+        Ok(())
+    }
+
+    #[test]
+    fn read_response_reads_chunked_bodies() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut raw = "HTTP/1.1 400 Bad Request\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n3\r\n{\"a\r\n4;ext=1\r\n\":1}\r\n0\r\n\r\n".as_bytes();
+        let response = read_response(&mut raw)?;
+        assert_eq!(response.status, 400);
+        assert_eq!(response.body, br#"{"a":1}"#);
+        assert!(!response.keep_alive);
+        Ok(())
+    }
+
+    #[test]
+    fn minimal_keeps_connections_alive() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let port = listener.local_addr()?.port();
+        let server = thread::spawn(move || -> io::Result<usize> {
+            let (stream, _) = listener.accept()?;
+            let mut reader = BufReader::new(stream);
+            let mut served = 0;
+            while served < 2 {
+                read_request(&mut reader)?;
+                reader
+                    .get_mut()
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}")?;
+                served += 1;
+            }
+            Ok(served)
+        });
+        let transport = Minimal::new();
+        for _ in 0..2 {
+            assert_eq!(transport.send(request(port)?)?, (200, "{}".into()));
+        }
+        assert_eq!(server.join().expect("server panicked")?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn minimal_reconnects_when_idle_connections_close() -> Result<(), Box<dyn Error + Send + Sync>>
+    {
+        // each connection is closed once its response is written
+        let (port, server) = serve(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}".to_vec(),
+            b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}".to_vec(),
+        ])?;
+        let transport = Minimal::new();
+        assert_eq!(transport.send(request(port)?)?, (200, "{}".into()));
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(transport.send(request(port)?)?, (200, "{}".into()));
+        server.join().expect("server panicked")?;
+        Ok(())
+    }
+
+    #[test]
+    fn minimal_does_not_resend_requests_after_they_are_written(
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let port = listener.local_addr()?.port();
+        let server = thread::spawn(move || -> io::Result<bool> {
+            let (stream, _) = listener.accept()?;
+            let mut reader = BufReader::new(stream);
+            read_request(&mut reader)?;
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}")?;
+            // the second request is received but its response is truncated
+            read_request(&mut reader)?;
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n{}")?;
+            drop(reader);
+            thread::sleep(Duration::from_millis(100));
+            listener.set_nonblocking(true)?;
+            Ok(listener.accept().is_ok())
+        });
+        let transport = Minimal::new();
+        assert_eq!(transport.send(request(port)?)?, (200, "{}".into()));
+        assert!(transport.send(request(port)?).is_err());
+        assert!(
+            !server.join().expect("server panicked")?,
+            "expected the request not to be resent"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn minimal_times_out_on_unresponsive_servers() -> Result<(), Box<dyn Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let port = listener.local_addr()?.port();
+        let (done, wait) = mpsc::channel::<()>();
+        let server = thread::spawn(move || -> io::Result<()> {
+            let (stream, _) = listener.accept()?;
+            let mut reader = BufReader::new(stream);
+            read_request(&mut reader)?;
+            // holds the connection open without responding
+            let _ = wait.recv();
+            Ok(())
+        });
+        let transport = Minimal::new().with_timeout(Duration::from_millis(50));
+        assert!(transport.send(request(port)?).is_err());
+        drop(done);
+        server.join().expect("server panicked")?;
+        Ok(())
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn minimal_decodes_gzipped_items() -> Result<(), Box<dyn Error>> {
+        use crate::{transport::tests::server::gzipped, Credentials, Table, DB};
+        let (port, server) = serve(vec![gzipped(br#"{"Item":{"value":{"S":"bar"}}}"#)?])?;
+        let db = DB::new(
+            Credentials::new("test", "test"),
+            Table::new(
+                "test",
+                "key",
+                "value",
+                "us-east-1".parse()?,
+                Some(format!("http://127.0.0.1:{}", port)),
+            ),
+            Minimal::new(),
+        );
+        assert_eq!(db.get("foo")?, Some("bar".into()));
+        server.join().expect("server panicked")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+//! Parsing of the INI formatted [shared credentials file](https://docs.aws.amazon.com/sdkref/latest/guide/file-location.html)
+use crate::{Credentials, CredentialsError};
+use std::{env, fs, path::PathBuf};
+
+/// Returns the location of the shared credentials file, honoring `AWS_SHARED_CREDENTIALS_FILE`
+fn credentials_file() -> Result<PathBuf, CredentialsError> {
+    if let Some(path) = env::var_os("AWS_SHARED_CREDENTIALS_FILE") {
+        return Ok(path.into());
+    }
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".aws").join("credentials"))
+        .ok_or_else(|| CredentialsError("unable to locate a home directory".into()))
+}
+
+/// Reads the credentials of a named profile from the shared credentials file
+pub(crate) fn load(name: &str) -> Result<Credentials, CredentialsError> {
+    let path = credentials_file()?;
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| CredentialsError(format!("unable to read {}: {}", path.display(), e)))?;
+    parse(&contents, name)
+}
+
+/// Extracts the credentials of a named profile from the contents of a shared credentials file
+pub(crate) fn parse(
+    contents: &str,
+    name: &str,
+) -> Result<Credentials, CredentialsError> {
+    let mut found = false;
+    let mut access_key_id = None;
+    let mut secret_access_key = None;
+    let mut session_token = None;
+    let mut current = None;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = Some(section.trim());
+            found |= current == Some(name);
+            continue;
+        }
+        if current != Some(name) {
+            continue;
+        }
+        if let Some(idx) = line.find('=') {
+            let value = line[idx + 1..].trim().to_owned();
+            match line[..idx].trim() {
+                "aws_access_key_id" => access_key_id = Some(value),
+                "aws_secret_access_key" => secret_access_key = Some(value),
+                "aws_session_token" => session_token = Some(value),
+                _ => (),
+            }
+        }
+    }
+    if !found {
+        return Err(CredentialsError(format!("profile {} not found", name)));
+    }
+    let required = |value: Option<String>, key: &str| {
+        value.ok_or_else(|| CredentialsError(format!("profile {} is missing {}", name, key)))
+    };
+    let credentials = Credentials::new(
+        required(access_key_id, "aws_access_key_id")?,
+        required(secret_access_key, "aws_secret_access_key")?,
+    );
+    Ok(Credentials {
+        aws_session_token: session_token,
+        ..credentials
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENTS: &str = "
+[default]
+aws_access_key_id = default-id
+aws_secret_access_key = default-secret
+
+# temporary credentials
+[dev]
+aws_access_key_id=dev-id
+aws_secret_access_key=dev-secret
+aws_session_token=dev-token
+
+[broken]
+aws_access_key_id = broken-id
+";
+
+    #[test]
+    fn parse_reads_named_profiles() -> Result<(), CredentialsError> {
+        let default = parse(CONTENTS, "default")?;
+        assert_eq!(default.aws_access_key_id, "default-id");
+        assert_eq!(default.aws_secret_access_key, "default-secret");
+        assert_eq!(default.aws_session_token, None);
+        let dev = parse(CONTENTS, "dev")?;
+        assert_eq!(dev.aws_access_key_id, "dev-id");
+        assert_eq!(dev.aws_secret_access_key, "dev-secret");
+        assert_eq!(dev.aws_session_token.as_deref(), Some("dev-token"));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_fails_on_missing_profiles_and_keys() {
+        assert_eq!(
+            parse(CONTENTS, "prod").err().map(|e| e.to_string()),
+            Some("profile prod not found".into())
+        );
+        assert_eq!(
+            parse(CONTENTS, "broken").err().map(|e| e.to_string()),
+            Some("profile broken is missing aws_secret_access_key".into())
+        );
+    }
+}
@@ -1 +1,72 @@
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{error::Error, fmt};
+
 include!(concat!(env!("OUT_DIR"), "/region.rs"));
+
+impl fmt::Display for Region {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl AsRef<str> for Region {
+    fn as_ref(&self) -> &str {
+        self.id()
+    }
+}
+
+/// Serializes as the region's short identifier, including that of a `Region::Custom`
+///
+/// A `Region::Custom` does not round trip. Its endpoint is not serialized so it can not be deserialized again
+#[cfg(feature = "serde")]
+impl Serialize for Region {
+    fn serialize<S>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.id())
+    }
+}
+
+/// Deserializes from a listed region's short identifier. Custom regions can not be deserialized
+/// because their endpoint is not known
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Region {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// An error returned when parsing an unrecognized region identifier
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseRegionError(String);
+
+impl ParseRegionError {
+    /// Returns the identifier which failed to parse
+    pub fn input(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ParseRegionError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(f, "invalid region: {}", self.0)
+    }
+}
+
+impl Error for ParseRegionError {}
@@ -1,9 +1,18 @@
-use crate::{Request, Transport};
+#[cfg(feature = "reqwest-async")]
+use crate::AsyncTransport;
+use crate::{
+    transport::{read_limited, Limited, ResponseTooLarge},
+    Request, Transport,
+};
+#[cfg(feature = "reqwest-async")]
+use futures_util::future::{BoxFuture, FutureExt};
+use http::HeaderMap;
 use reqwest::blocking::Client;
-use std::error::Error;
+use std::{error::Error, io::Read, time::Duration};
 
 pub struct Reqwest {
     client: Client,
+    max_response_bytes: Option<u64>,
 }
 
 impl Default for Reqwest {
@@ -13,24 +22,324 @@ impl Default for Reqwest {
 }
 
 impl Reqwest {
+    /// Returns a new instance backed by a default `Client`, which never times out
     pub fn new() -> Self {
+        Self::from_client(Client::new())
+    }
+
+    /// Returns a new instance backed by a `Client` configured by the caller
+    ///
+    /// `Client`s hold their connection pool behind a reference count so a clone of a shared client
+    /// may be provided to each `DB` to reuse its connections
+    ///
+    /// # Example
+    ///
+    /// ```rust ,no_run
+    /// # use tiny_dynamo::{reqwest_transport::Reqwest, Credentials, Table, DB};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = reqwest::blocking::Client::builder()
+    ///     .proxy(reqwest::Proxy::https("http://proxy.internal:3128")?)
+    ///     .build()?;
+    /// let db = DB::new(
+    ///     Credentials::from_env()?,
+    ///     Table::new("table-name", "key-attr-name", "value-attr-name", "us-east-1".parse()?, None),
+    ///     Reqwest::from_client(client.clone()),
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_client(client: Client) -> Self {
         Reqwest {
-            client: Client::new(),
+            client,
+            max_response_bytes: None,
         }
     }
+
+    /// Returns a new instance whose requests fail once `timeout` has elapsed, from connecting
+    /// until the response body has been read
+    pub fn with_timeout(timeout: Duration) -> Result<Self, reqwest::Error> {
+        Ok(Self::from_client(
+            Client::builder().timeout(timeout).build()?,
+        ))
+    }
+
+    /// Limits the size of response bodies, which are otherwise read in full whatever their size
+    ///
+    /// Responses whose body exceeds `max` bytes fail with a `transport::ResponseTooLarge` error as soon as the
+    /// limit is reached, without buffering the rest of the body. Compressed bodies are limited both by their
+    /// compressed and their decompressed size. Reads of a body returned by `send_streaming` fail with an `io::Error`
+    /// wrapping a `transport::ResponseTooLarge` once the limit is exceeded
+    pub fn with_max_response_bytes(
+        mut self,
+        max: u64,
+    ) -> Self {
+        self.max_response_bytes = Some(max);
+        self
+    }
 }
 
 impl Transport for Reqwest {
     fn send(
         &self,
         signed: Request,
-    ) -> Result<(u16, String), Box<dyn Error>> {
+    ) -> Result<(u16, String), Box<dyn Error + Send + Sync>> {
+        self.send_full(signed)
+            .map(|(status, _, body)| (status, body))
+    }
+
+    fn send_full(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, HeaderMap, String), Box<dyn Error + Send + Sync>> {
+        // the request is owned so its headers and body are moved rather than copied
+        let (parts, body) = signed.into_parts();
         let resp = self
             .client
-            .post(signed.uri().to_string())
-            .headers(signed.headers().clone())
-            .body(signed.body().clone())
+            .post(parts.uri.to_string())
+            .headers(parts.headers)
+            .body(body)
             .send()?;
-        Ok((resp.status().as_u16(), resp.text()?))
+        let status = resp.status().as_u16();
+        let headers = resp.headers().clone();
+        let bytes = match self.max_response_bytes {
+            Some(limit) => {
+                // fail before reading anything when the length is known up front
+                if resp.content_length().map_or(false, |len| len > limit) {
+                    return Err(Box::new(ResponseTooLarge(limit)));
+                }
+                read_limited(resp, limit)?
+            }
+            None => resp.bytes()?.to_vec(),
+        };
+        #[cfg(feature = "gzip")]
+        let body = {
+            let encoding = headers
+                .get(reqwest::header::CONTENT_ENCODING)
+                .and_then(|value| value.to_str().ok());
+            match self.max_response_bytes {
+                Some(limit) => crate::transport::decode_body_limited(encoding, bytes, limit)?,
+                None => crate::transport::decode_body(encoding, bytes)?,
+            }
+        };
+        #[cfg(not(feature = "gzip"))]
+        let body = String::from_utf8(bytes)?;
+        Ok((status, headers, body))
+    }
+
+    fn send_streaming(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, Box<dyn Read + Send>), Box<dyn Error + Send + Sync>> {
+        let (parts, body) = signed.into_parts();
+        let resp = self
+            .client
+            .post(parts.uri.to_string())
+            .headers(parts.headers)
+            .body(body)
+            .send()?;
+        let status = resp.status().as_u16();
+        #[cfg(feature = "gzip")]
+        let body = {
+            let encoding = resp
+                .headers()
+                .get(reqwest::header::CONTENT_ENCODING)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned);
+            crate::transport::decode_reader(encoding.as_deref(), resp)
+        };
+        #[cfg(not(feature = "gzip"))]
+        let body: Box<dyn Read + Send> = Box::new(resp);
+        let body = match self.max_response_bytes {
+            Some(limit) => Box::new(Limited::new(body, limit)),
+            None => body,
+        };
+        Ok((status, body))
+    }
+}
+
+/// Provides an `AsyncTransport` backed by an async `reqwest::Client`, for use with an `AsyncDB`
+#[cfg(feature = "reqwest-async")]
+pub struct AsyncReqwest {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "reqwest-async")]
+impl Default for AsyncReqwest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "reqwest-async")]
+impl AsyncReqwest {
+    pub fn new() -> Self {
+        AsyncReqwest {
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[cfg(feature = "reqwest-async")]
+impl AsyncTransport for AsyncReqwest {
+    fn send(
+        &self,
+        signed: Request,
+    ) -> BoxFuture<'_, Result<(u16, String), Box<dyn Error + Send + Sync>>> {
+        async move {
+            let (parts, body) = signed.into_parts();
+            let resp = self
+                .client
+                .post(parts.uri.to_string())
+                .headers(parts.headers)
+                .body(body)
+                .send()
+                .await?;
+            let status = resp.status().as_u16();
+            #[cfg(feature = "gzip")]
+            let body = {
+                let encoding = resp
+                    .headers()
+                    .get(reqwest::header::CONTENT_ENCODING)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_owned);
+                crate::transport::decode_body(encoding.as_deref(), resp.bytes().await?.to_vec())?
+            };
+            #[cfg(not(feature = "gzip"))]
+            let body = resp.text().await?;
+            Ok((status, body))
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::tests::server::{response, serve};
+    use std::{net::TcpListener, thread};
+
+    #[test]
+    fn with_timeout_fails_unanswered_requests() -> Result<(), Box<dyn Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let port = listener.local_addr()?.port();
+        // accept the connection but never respond
+        let server = thread::spawn(move || listener.accept().map(|(stream, _)| stream));
+        let request = http::Request::builder()
+            .method("POST")
+            .uri(format!("http://127.0.0.1:{}", port))
+            .body(b"{}".to_vec())?;
+        assert!(Reqwest::with_timeout(Duration::from_millis(100))?
+            .send(request)
+            .is_err());
+        drop(server.join().expect("server panicked")?);
+        Ok(())
+    }
+
+    #[test]
+    fn with_max_response_bytes_rejects_large_bodies() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let (port, server) = serve(vec![
+            response("200 OK", "", br#"{"Item":null}"#),
+            // chunked bodies have no length up front
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\nd\r\n{\"Item\":null}\r\n0\r\n\r\n".to_vec(),
+            response("200 OK", "", b"{}"),
+        ])?;
+        let transport = Reqwest::new().with_max_response_bytes(4);
+        let request = || {
+            http::Request::builder()
+                .method("POST")
+                .uri(format!("http://127.0.0.1:{}", port))
+                .body(b"{}".to_vec())
+        };
+        for _ in 0..2 {
+            let err = transport.send(request()?).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<ResponseTooLarge>()
+                    .map(ResponseTooLarge::limit),
+                Some(4)
+            );
+        }
+        assert_eq!(transport.send(request()?)?, (200, "{}".into()));
+        server.join().expect("server panicked")?;
+        Ok(())
+    }
+
+    #[test]
+    fn send_streaming_reads_response_bodies() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let (port, server) = serve(vec![response("200 OK", "", br#"{"Item":null}"#)])?;
+        let request = http::Request::builder()
+            .method("POST")
+            .uri(format!("http://127.0.0.1:{}", port))
+            .body(b"{}".to_vec())?;
+        let (status, mut body) = Reqwest::new().send_streaming(request)?;
+        let mut text = String::new();
+        body.read_to_string(&mut text)?;
+        assert_eq!((status, text.as_str()), (200, r#"{"Item":null}"#));
+        server.join().expect("server panicked")?;
+        Ok(())
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn decodes_gzipped_bodies() -> Result<(), Box<dyn Error + Send + Sync>> {
+        use crate::{transport::tests::server::gzipped, Credentials, Table, DB};
+        let item = br#"{"Item":{"value":{"S":"bar"}}}"#;
+        let (port, server) = serve(vec![gzipped(item)?, gzipped(item)?])?;
+        let db = DB::new(
+            Credentials::new("test", "test"),
+            Table {
+                accept_gzip: true,
+                ..Table::new(
+                    "test",
+                    "key",
+                    "value",
+                    "us-east-1".parse()?,
+                    Some(format!("http://127.0.0.1:{}", port)),
+                )
+            },
+            Reqwest::new(),
+        );
+        assert_eq!(db.get("foo")?, Some("bar".into()));
+        let (status, mut body) = Reqwest::new().send_streaming(db.get_item_req("foo")?)?;
+        let mut text = String::new();
+        body.read_to_string(&mut text)?;
+        assert_eq!((status, text.as_bytes()), (200, &item[..]));
+        server.join().expect("server panicked")?;
+        Ok(())
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn with_max_response_bytes_limits_decompressed_bodies(
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        use crate::transport::tests::server::gzipped;
+        // a body which compresses to far fewer bytes than the limit
+        let expanding = format!(
+            r#"{{"Item":{{"value":{{"S":"{}"}}}}}}"#,
+            "a".repeat(100_000)
+        );
+        let response = gzipped(expanding.as_bytes())?;
+        assert!(response.len() < 1_000);
+        let (port, server) = serve(vec![response.clone(), response])?;
+        let transport = Reqwest::new().with_max_response_bytes(1_000);
+        let request = || {
+            http::Request::builder()
+                .method("POST")
+                .uri(format!("http://127.0.0.1:{}", port))
+                .body(b"{}".to_vec())
+        };
+        let err = transport.send(request()?).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ResponseTooLarge>(),
+            Some(&ResponseTooLarge(1_000))
+        );
+        let (_, mut body) = transport.send_streaming(request()?)?;
+        let err = body.read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(
+            err.get_ref()
+                .and_then(|err| err.downcast_ref::<ResponseTooLarge>()),
+            Some(&ResponseTooLarge(1_000))
+        );
+        server.join().expect("server panicked")?;
+        Ok(())
     }
 }
@@ -0,0 +1,93 @@
+//! An `AsyncTransport` backed by [surf](https://crates.io/crates/surf), for applications running
+//! on [async-std](https://crates.io/crates/async-std) rather than tokio
+use crate::{AsyncTransport, Request, StrErr};
+use futures_util::future::{BoxFuture, FutureExt};
+use std::error::Error;
+use surf_client::{http::Method, Client, Url};
+
+/// Provides an `AsyncTransport` backed by a `surf::Client`, for use with an `AsyncDB`
+pub struct Surf {
+    client: Client,
+}
+
+impl Default for Surf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Surf {
+    /// Returns a new instance backed by a default `surf::Client`
+    pub fn new() -> Self {
+        Self::from_client(Client::new())
+    }
+
+    /// Returns a new instance backed by a `surf::Client` configured by the caller
+    pub fn from_client(client: Client) -> Self {
+        Surf { client }
+    }
+}
+
+impl AsyncTransport for Surf {
+    fn send(
+        &self,
+        signed: Request,
+    ) -> BoxFuture<'_, Result<(u16, String), Box<dyn Error + Send + Sync>>> {
+        async move {
+            let (parts, body) = signed.into_parts();
+            let mut request =
+                surf_client::Request::new(Method::Post, Url::parse(&parts.uri.to_string())?);
+            for (name, value) in &parts.headers {
+                request.append_header(name.as_str(), value.to_str()?);
+            }
+            // set after the signed headers so that their content type is kept
+            request.set_body(body);
+            let mut resp = self
+                .client
+                .send(request)
+                .await
+                .map_err(|err| StrErr(err.to_string()))?;
+            let status = u16::from(resp.status());
+            let bytes = resp
+                .body_bytes()
+                .await
+                .map_err(|err| StrErr(err.to_string()))?;
+            #[cfg(feature = "gzip")]
+            let body = {
+                let encoding = resp
+                    .header("Content-Encoding")
+                    .map(|values| values.as_str().to_owned());
+                crate::transport::decode_body(encoding.as_deref(), bytes)?
+            };
+            #[cfg(not(feature = "gzip"))]
+            let body = String::from_utf8(bytes)?;
+            Ok((status, body))
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::tests::server::{response, serve};
+
+    #[test]
+    fn surf_sends_requests() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let (port, server) = serve(vec![response("200 OK", "", b"{}")])?;
+        let request = http::Request::builder()
+            .method("POST")
+            .uri(format!("http://127.0.0.1:{}", port))
+            .header("Content-Type", "application/x-amz-json-1.0")
+            .body(br#"{"TableName":"test"}"#.to_vec())?;
+        let response = async_std::task::block_on(Surf::new().send(request))?;
+        assert_eq!(response, (200, "{}".into()));
+        let received = server.join().expect("server panicked")?;
+        assert_eq!(
+            received[0].headers["content-type"],
+            "application/x-amz-json-1.0"
+        );
+        assert_eq!(received[0].body, br#"{"TableName":"test"}"#);
+        Ok(())
+    }
+}
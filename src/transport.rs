@@ -0,0 +1,972 @@
+//! `Transport` implementations that layer additional behavior over other transports
+use crate::{AWSError, Error, Request, StrErr, Transport};
+use http::HeaderMap;
+use serde_json::{json, Value};
+use std::{
+    collections::{hash_map::RandomState, HashMap, VecDeque},
+    error::Error as StdError,
+    hash::{BuildHasher, Hasher},
+    io::{Cursor, Read},
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Provides a `Transport` which bounds the wall clock time of any inner `Transport`
+/// that may not support timeouts natively
+///
+/// Each request is sent on a worker thread. If the inner transport does not respond
+/// within the configured timeout an `Error::Timeout` is returned. Because threads
+/// can not be cancelled, a timed out worker is left to finish in the background and
+/// its eventual response is discarded.
+pub struct WithTimeout<T> {
+    inner: Arc<T>,
+    timeout: Duration,
+}
+
+impl<T> WithTimeout<T> {
+    /// Wraps `inner` enforcing a deadline of `timeout` on each request
+    pub fn new(
+        inner: T,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            timeout,
+        }
+    }
+}
+
+impl<T> Transport for WithTimeout<T>
+where
+    T: Transport + Send + Sync + 'static,
+{
+    fn send(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, String), Box<dyn StdError + Send + Sync>> {
+        self.send_full(signed)
+            .map(|(status, _, body)| (status, body))
+    }
+
+    fn send_full(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, HeaderMap, String), Box<dyn StdError + Send + Sync>> {
+        let (tx, rx) = mpsc::channel();
+        let inner = Arc::clone(&self.inner);
+        let worker = thread::spawn(move || {
+            let _ = tx.send(inner.send_full(signed));
+        });
+        match rx.recv_timeout(self.timeout) {
+            Ok(result) => {
+                let _ = worker.join();
+                result
+            }
+            Err(RecvTimeoutError::Timeout) => Err(Box::new(Error::Timeout)),
+            Err(RecvTimeoutError::Disconnected) => {
+                let _ = worker.join();
+                Err(Box::new(StrErr("transport worker panicked".into())))
+            }
+        }
+    }
+
+    /// Only the time taken for the inner transport to return a response is bounded, not the time taken
+    /// to read its body
+    fn send_streaming(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, Box<dyn Read + Send>), Box<dyn StdError + Send + Sync>> {
+        let (tx, rx) = mpsc::channel();
+        let inner = Arc::clone(&self.inner);
+        let worker = thread::spawn(move || {
+            let _ = tx.send(inner.send_streaming(signed));
+        });
+        match rx.recv_timeout(self.timeout) {
+            Ok(result) => {
+                let _ = worker.join();
+                result
+            }
+            Err(RecvTimeoutError::Timeout) => Err(Box::new(Error::Timeout)),
+            Err(RecvTimeoutError::Disconnected) => {
+                let _ = worker.join();
+                Err(Box::new(StrErr("transport worker panicked".into())))
+            }
+        }
+    }
+}
+
+/// Provides a `Transport` which logs the requests and responses of any inner `Transport`
+/// at debug level via the [log](https://crates.io/crates/log) crate
+///
+/// Credential bearing headers are redacted before being logged
+#[cfg(feature = "log")]
+pub struct Logging<T> {
+    inner: T,
+}
+
+#[cfg(feature = "log")]
+impl<T> Logging<T> {
+    /// Wraps `inner` logging each request and response
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+}
+
+#[cfg(feature = "log")]
+impl<T> Transport for Logging<T>
+where
+    T: Transport,
+{
+    fn send(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, String), Box<dyn StdError + Send + Sync>> {
+        self.send_full(signed)
+            .map(|(status, _, body)| (status, body))
+    }
+
+    fn send_full(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, HeaderMap, String), Box<dyn StdError + Send + Sync>> {
+        log::debug!(
+            "request {} {} headers={:?} body={}",
+            signed.method(),
+            signed.uri(),
+            redacted(signed.headers()),
+            String::from_utf8_lossy(signed.body())
+        );
+        let result = self.inner.send_full(signed);
+        match &result {
+            Ok((status, _, body)) => log::debug!("response status={} body={}", status, body),
+            Err(err) => log::debug!("response error={}", err),
+        }
+        result
+    }
+
+    /// Streamed response bodies are not logged
+    fn send_streaming(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, Box<dyn Read + Send>), Box<dyn StdError + Send + Sync>> {
+        log::debug!(
+            "request {} {} headers={:?} body={}",
+            signed.method(),
+            signed.uri(),
+            redacted(signed.headers()),
+            String::from_utf8_lossy(signed.body())
+        );
+        let result = self.inner.send_streaming(signed);
+        match &result {
+            Ok((status, _)) => log::debug!("response status={} body=<streamed>", status),
+            Err(err) => log::debug!("response error={}", err),
+        }
+        result
+    }
+}
+
+/// Provides a `Transport` which resends requests that failed with a transient error
+///
+/// Responses with a 5xx status and those whose error is `AWSError::is_retryable`, i.e. throttling, are
+/// retried up to a maximum number of attempts, waiting between attempts with exponential backoff and
+/// full jitter. Signed requests remain valid for several minutes so they are resent as is without being
+/// signed again. The last response is returned once attempts are exhausted
+pub struct Retry<T> {
+    inner: T,
+    max_attempts: u32,
+    base_delay: Duration,
+}
+
+impl<T> Retry<T> {
+    /// Wraps `inner` sending each request at most `max_attempts` times
+    pub fn new(
+        inner: T,
+        max_attempts: u32,
+    ) -> Self {
+        Self {
+            inner,
+            max_attempts: max_attempts.max(1),
+            base_delay: Duration::from_millis(50),
+        }
+    }
+
+    /// Configures the delay the backoff between attempts grows from. Defaults to 50 milliseconds
+    pub fn with_base_delay(
+        mut self,
+        base_delay: Duration,
+    ) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Returns a random delay up to the base delay doubled for each prior attempt
+    fn delay(
+        &self,
+        attempt: u32,
+    ) -> Duration {
+        let ceiling = self.base_delay * 2_u32.saturating_pow(attempt.min(16) - 1);
+        let jitter = RandomState::new().build_hasher().finish() % 1_000;
+        ceiling * jitter as u32 / 1_000
+    }
+}
+
+impl<T> Transport for Retry<T>
+where
+    T: Transport,
+{
+    fn send(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, String), Box<dyn StdError + Send + Sync>> {
+        self.send_full(signed)
+            .map(|(status, _, body)| (status, body))
+    }
+
+    fn send_full(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, HeaderMap, String), Box<dyn StdError + Send + Sync>> {
+        let mut attempt = 1;
+        loop {
+            let (status, headers, body) = self.inner.send_full(duplicate(&signed))?;
+            if attempt >= self.max_attempts || !retryable(status, &body) {
+                return Ok((status, headers, body));
+            }
+            thread::sleep(self.delay(attempt));
+            attempt += 1;
+        }
+    }
+
+    /// Successful response bodies are streamed. Error response bodies are read in full to decide whether
+    /// they may be retried
+    fn send_streaming(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, Box<dyn Read + Send>), Box<dyn StdError + Send + Sync>> {
+        let mut attempt = 1;
+        loop {
+            let (status, mut body) = self.inner.send_streaming(duplicate(&signed))?;
+            if attempt >= self.max_attempts || status < 400 {
+                return Ok((status, body));
+            }
+            let mut text = String::new();
+            body.read_to_string(&mut text)?;
+            if !retryable(status, &text) {
+                return Ok((status, Box::new(Cursor::new(text.into_bytes()))));
+            }
+            thread::sleep(self.delay(attempt));
+            attempt += 1;
+        }
+    }
+}
+
+/// Returns true when a response indicates a transient failure
+fn retryable(
+    status: u16,
+    body: &str,
+) -> bool {
+    status >= 500
+        || (status != 200
+            && serde_json::from_str::<AWSError>(body)
+                .map(|err| err.is_retryable())
+                .unwrap_or_default())
+}
+
+/// Returns a copy of a request, which `http::Request` does not otherwise provide
+fn duplicate(request: &Request) -> Request {
+    let mut copy = Request::new(request.body().clone());
+    *copy.method_mut() = request.method().clone();
+    *copy.uri_mut() = request.uri().clone();
+    *copy.version_mut() = request.version();
+    *copy.headers_mut() = request.headers().clone();
+    copy
+}
+
+/// Provides an in-memory `Transport` which stores items in a `HashMap` rather than sending requests
+/// over the network, useful as a fake in unit tests
+///
+/// `PutItem`, `GetItem` and `DeleteItem` requests are answered as DynamoDB would, including values
+/// returned by `ReturnValues`. Condition and projection expressions are ignored. Requests for any
+/// other operation fail with an `UnknownOperationException`
+///
+/// # Example
+///
+/// ```rust
+/// # use tiny_dynamo::{transport::Memory, Credentials, Table, DB};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let db = DB::new(
+///     Credentials::new("test", "test"),
+///     Table::new("test", "key", "value", "us-east-1".parse()?, None),
+///     Memory::new("key"),
+/// );
+/// db.set("foo", "bar")?;
+/// assert_eq!(db.get("foo")?, Some("bar".into()));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Memory {
+    key_name: String,
+    items: Mutex<HashMap<String, String>>,
+}
+
+impl Memory {
+    /// Returns an empty store for a table whose items are keyed by the `key_name` attribute
+    pub fn new(key_name: impl AsRef<str>) -> Self {
+        Self {
+            key_name: key_name.as_ref().to_owned(),
+            items: Mutex::default(),
+        }
+    }
+
+    /// Returns the string form of the key attribute within an item or key
+    fn key(
+        &self,
+        item: &Value,
+    ) -> Result<String, Box<dyn StdError + Send + Sync>> {
+        item[self.key_name.as_str()]["S"]
+            .as_str()
+            .map(str::to_owned)
+            .ok_or_else(|| format!("missing key attribute {}", self.key_name).into())
+    }
+}
+
+impl Transport for Memory {
+    fn send(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, String), Box<dyn StdError + Send + Sync>> {
+        let target = signed
+            .headers()
+            .get("X-Amz-Target")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        let operation = target.rsplit('.').next().unwrap_or_default().to_owned();
+        let input: Value = serde_json::from_slice(signed.body())?;
+        let all_old = input["ReturnValues"] == "ALL_OLD";
+        let mut items = self
+            .items
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let old = match operation.as_str() {
+            "PutItem" => items.insert(self.key(&input["Item"])?, input["Item"].to_string()),
+            "GetItem" => {
+                return Ok((
+                    200,
+                    match items.get(&self.key(&input["Key"])?) {
+                        Some(item) => format!(r#"{{"Item":{}}}"#, item),
+                        None => "{}".into(),
+                    },
+                ))
+            }
+            "DeleteItem" => items.remove(&self.key(&input["Key"])?),
+            _ => {
+                return Ok((
+                    400,
+                    json!({
+                        "__type": "com.amazon.coral.service#UnknownOperationException",
+                        "Message": format!("{} is not supported", target)
+                    })
+                    .to_string(),
+                ))
+            }
+        };
+        Ok((
+            200,
+            match old {
+                Some(item) if all_old => format!(r#"{{"Attributes":{}}}"#, item),
+                _ => "{}".into(),
+            },
+        ))
+    }
+}
+
+/// Provides a `Transport` which answers every request with a constant response, like `Const`, or each
+/// request in turn with a scripted response, while recording each request it receives so that tests may
+/// assert on them afterwards
+///
+/// Clones of a `Mock` share their responses and the requests they record so a clone may be handed to a `DB`
+/// while the original is kept for inspecting its requests
+///
+/// # Example
+///
+/// ```rust
+/// # use tiny_dynamo::{transport::Mock, Credentials, Table, DB};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mock = Mock::new(200, "{}");
+/// let db = DB::new(
+///     Credentials::new("test", "test"),
+///     Table::new("test", "key", "value", "us-east-1".parse()?, None),
+///     mock.clone(),
+/// );
+/// db.set("foo", "bar")?;
+/// assert_eq!(
+///     mock.requests()[0].headers()["X-Amz-Target"],
+///     "DynamoDB_20120810.PutItem"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Mock {
+    responses: Arc<Mutex<VecDeque<(u16, String)>>>,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl Mock {
+    /// Returns a new instance answering every request with `status` and `body`
+    pub fn new(
+        status: u16,
+        body: impl AsRef<str>,
+    ) -> Self {
+        Self::scripted(Some((status, body)))
+    }
+
+    /// Returns a new instance answering each request in turn with the next of `responses`, repeating the
+    /// last once the others are used up, i.e. to answer each page of a paginated `Scan`
+    ///
+    /// Panics when `responses` is empty
+    pub fn scripted<B>(responses: impl IntoIterator<Item = (u16, B)>) -> Self
+    where
+        B: AsRef<str>,
+    {
+        let responses = responses
+            .into_iter()
+            .map(|(status, body)| (status, body.as_ref().to_owned()))
+            .collect::<VecDeque<_>>();
+        assert!(!responses.is_empty(), "expected at least one response");
+        Self {
+            responses: Arc::new(Mutex::new(responses)),
+            requests: Arc::default(),
+        }
+    }
+
+    /// Returns a copy of each request received so far, in the order they were sent
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .map(duplicate)
+            .collect()
+    }
+
+    /// Returns the JSON body of each request received so far, in the order they were sent. Bodies which
+    /// are not JSON are returned as `Value::Null`
+    pub fn bodies(&self) -> Vec<Value> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .map(|request| serde_json::from_slice(request.body()).unwrap_or(Value::Null))
+            .collect()
+    }
+}
+
+impl Transport for Mock {
+    fn send(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, String), Box<dyn StdError + Send + Sync>> {
+        self.requests
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(signed);
+        let mut responses = self
+            .responses
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let response = if responses.len() > 1 {
+            responses.pop_front()
+        } else {
+            responses.front().cloned()
+        };
+        Ok(response.expect("expected at least one response"))
+    }
+}
+
+/// Decodes a response body, decompressing it when its `Content-Encoding` is gzip or deflate.
+/// See `Table::accept_gzip`
+///
+/// Responses may also arrive compressed when a proxy between a client and DynamoDB compresses them.
+/// This is useful for custom `Transport` implementations
+#[cfg(feature = "gzip")]
+pub fn decode_body(
+    content_encoding: Option<&str>,
+    body: Vec<u8>,
+) -> Result<String, Box<dyn StdError + Send + Sync>> {
+    let mut decompressed = String::new();
+    match content_encoding {
+        Some(encoding) if encoding.eq_ignore_ascii_case("gzip") => {
+            flate2::read::GzDecoder::new(body.as_slice()).read_to_string(&mut decompressed)?;
+        }
+        // http's deflate encoding is zlib wrapped
+        Some(encoding) if encoding.eq_ignore_ascii_case("deflate") => {
+            flate2::read::ZlibDecoder::new(body.as_slice()).read_to_string(&mut decompressed)?;
+        }
+        _ => return Ok(String::from_utf8(body)?),
+    }
+    Ok(decompressed)
+}
+
+/// Wraps a response body reader, decompressing it as it is read when its `Content-Encoding` is gzip or deflate.
+/// See `decode_body`
+///
+/// This is useful for custom `Transport` implementations of `Transport::send_streaming`
+#[cfg(feature = "gzip")]
+pub fn decode_reader(
+    content_encoding: Option<&str>,
+    body: impl Read + Send + 'static,
+) -> Box<dyn Read + Send> {
+    match content_encoding {
+        Some(encoding) if encoding.eq_ignore_ascii_case("gzip") => {
+            Box::new(flate2::read::GzDecoder::new(body))
+        }
+        Some(encoding) if encoding.eq_ignore_ascii_case("deflate") => {
+            Box::new(flate2::read::ZlibDecoder::new(body))
+        }
+        _ => Box::new(body),
+    }
+}
+
+/// An error returned by a transport configured with a maximum response size when a response body exceeds it,
+/// i.e. `Reqwest::with_max_response_bytes`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseTooLarge(pub(crate) u64);
+
+impl ResponseTooLarge {
+    /// Returns the maximum number of bytes the transport was configured to accept
+    pub fn limit(&self) -> u64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for ResponseTooLarge {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(f, "response body exceeds the limit of {} bytes", self.0)
+    }
+}
+
+impl StdError for ResponseTooLarge {}
+
+/// Reads a response body, failing with `ResponseTooLarge` as soon as more than `limit` bytes have been read
+/// rather than buffering the whole body
+#[cfg(any(feature = "reqwest", feature = "fastly"))]
+pub(crate) fn read_limited(
+    body: impl std::io::Read,
+    limit: u64,
+) -> Result<Vec<u8>, Box<dyn StdError + Send + Sync>> {
+    use std::io::Read;
+    let mut bytes = Vec::new();
+    body.take(limit.saturating_add(1)).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > limit {
+        return Err(Box::new(ResponseTooLarge(limit)));
+    }
+    Ok(bytes)
+}
+
+/// Decodes a response body like `decode_body`, failing with `ResponseTooLarge` as soon as more than `limit` bytes
+/// have been decompressed so that a small compressed body can not expand without bound
+#[cfg(all(feature = "gzip", any(feature = "reqwest", feature = "fastly")))]
+pub(crate) fn decode_body_limited(
+    content_encoding: Option<&str>,
+    body: Vec<u8>,
+    limit: u64,
+) -> Result<String, Box<dyn StdError + Send + Sync>> {
+    let decoded = read_limited(
+        decode_reader(content_encoding, std::io::Cursor::new(body)),
+        limit,
+    )?;
+    Ok(String::from_utf8(decoded)?)
+}
+
+/// Wraps a streamed response body, failing reads with an `io::Error` wrapping a `ResponseTooLarge` once more
+/// than `limit` bytes have been read
+#[cfg(any(feature = "reqwest", feature = "fastly"))]
+pub(crate) struct Limited<R> {
+    inner: R,
+    limit: u64,
+    read: u64,
+}
+
+#[cfg(any(feature = "reqwest", feature = "fastly"))]
+impl<R> Limited<R> {
+    pub(crate) fn new(
+        inner: R,
+        limit: u64,
+    ) -> Self {
+        Self {
+            inner,
+            limit,
+            read: 0,
+        }
+    }
+}
+
+#[cfg(any(feature = "reqwest", feature = "fastly"))]
+impl<R: std::io::Read> std::io::Read for Limited<R> {
+    fn read(
+        &mut self,
+        buf: &mut [u8],
+    ) -> std::io::Result<usize> {
+        // at most one byte past the limit is read so that exceeding it is detected
+        let max = self
+            .limit
+            .saturating_add(1)
+            .saturating_sub(self.read)
+            .min(buf.len() as u64) as usize;
+        let read = self.inner.read(&mut buf[..max])?;
+        self.read += read as u64;
+        if self.read > self.limit {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                ResponseTooLarge(self.limit),
+            ));
+        }
+        Ok(read)
+    }
+}
+
+#[cfg(feature = "log")]
+fn redacted(headers: &HeaderMap) -> Vec<(&str, &str)> {
+    headers
+        .iter()
+        .map(|(name, value)| match name.as_str() {
+            "authorization" | "x-amz-security-token" => (name.as_str(), "<redacted>"),
+            _ => (name.as_str(), value.to_str().unwrap_or("<binary>")),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{tests::test_db, Const};
+
+    /// Local HTTP server fixtures shared by the tests of each transport. Transports are optional so which of these
+    /// are used depends on the features enabled
+    #[allow(dead_code)]
+    pub(crate) mod server {
+        use super::*;
+        use std::{
+            io::{self, BufRead, BufReader, Write},
+            net::TcpListener,
+        };
+
+        /// A request received by a local server. See `serve`
+        pub(crate) struct Received {
+            /// Header names and values, with names in lower case
+            pub(crate) headers: HashMap<String, String>,
+            pub(crate) body: Vec<u8>,
+        }
+
+        /// Reads the head and body of a request
+        pub(crate) fn read_request(reader: &mut impl BufRead) -> io::Result<Received> {
+            let mut headers = HashMap::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line)?;
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    headers.insert(name.to_lowercase(), value.trim().to_owned());
+                }
+            }
+            let len = headers
+                .get("content-length")
+                .and_then(|len| len.parse().ok())
+                .unwrap_or_default();
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body)?;
+            Ok(Received { headers, body })
+        }
+
+        /// Answers each connection accepted on a local port with the next of `responses`, returning the port and a
+        /// handle yielding the requests received
+        pub(crate) fn serve(
+            responses: Vec<Vec<u8>>
+        ) -> io::Result<(u16, thread::JoinHandle<io::Result<Vec<Received>>>)> {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let port = listener.local_addr()?.port();
+            let server = thread::spawn(move || {
+                let mut received = Vec::with_capacity(responses.len());
+                for response in responses {
+                    let (stream, _) = listener.accept()?;
+                    let mut reader = BufReader::new(stream);
+                    received.push(read_request(&mut reader)?);
+                    reader.get_mut().write_all(&response)?;
+                }
+                Ok(received)
+            });
+            Ok((port, server))
+        }
+
+        /// Returns a response closing its connection with a status line, i.e. `200 OK`, any additional header lines
+        /// and a body
+        pub(crate) fn response(
+            status: &str,
+            headers: &str,
+            body: &[u8],
+        ) -> Vec<u8> {
+            let mut response = format!(
+                "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                headers,
+                body.len()
+            )
+            .into_bytes();
+            response.extend(body);
+            response
+        }
+
+        /// Returns a response whose body is gzip compressed
+        #[cfg(feature = "gzip")]
+        pub(crate) fn gzipped(body: &[u8]) -> io::Result<Vec<u8>> {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(body)?;
+            Ok(response(
+                "200 OK",
+                "Content-Encoding: gzip\r\n",
+                &encoder.finish()?,
+            ))
+        }
+    }
+
+    struct Slow(Duration);
+
+    impl Transport for Slow {
+        fn send(
+            &self,
+            _: Request,
+        ) -> Result<(u16, String), Box<dyn StdError + Send + Sync>> {
+            thread::sleep(self.0);
+            Ok((200, "{}".into()))
+        }
+    }
+
+    #[test]
+    fn with_timeout_passes_through_timely_responses() -> Result<(), Box<dyn StdError + Send + Sync>>
+    {
+        let transport = WithTimeout::new(Const(200, "{}".into()), Duration::from_secs(1));
+        assert_eq!(transport.send(Request::default())?, (200, "{}".into()));
+        Ok(())
+    }
+
+    #[test]
+    fn with_timeout_errors_on_slow_responses() {
+        let transport = WithTimeout::new(Slow(Duration::from_secs(1)), Duration::from_millis(10));
+        let err = transport
+            .send(Request::default())
+            .expect_err("expected timeout");
+        assert!(matches!(err.downcast_ref::<Error>(), Some(Error::Timeout)));
+    }
+
+    #[test]
+    fn with_timeout_preserves_inner_errors() {
+        struct TooLarge;
+
+        impl Transport for TooLarge {
+            fn send(
+                &self,
+                _: Request,
+            ) -> Result<(u16, String), Box<dyn StdError + Send + Sync>> {
+                Err(Box::new(ResponseTooLarge(1)))
+            }
+        }
+
+        let err = WithTimeout::new(TooLarge, Duration::from_secs(1))
+            .send(Request::default())
+            .expect_err("expected error");
+        assert_eq!(
+            err.downcast_ref::<ResponseTooLarge>(),
+            Some(&ResponseTooLarge(1))
+        );
+    }
+
+    #[test]
+    fn retry_resends_transient_failures() -> Result<(), Box<dyn StdError + Send + Sync>> {
+        let throttled = r#"{"__type":"com.amazonaws.dynamodb.v20120810#ProvisionedThroughputExceededException","Message":"slow down"}"#;
+        let transport = Retry::new(
+            Mock::scripted([(400, throttled), (500, ""), (200, "{}")]),
+            3,
+        )
+        .with_base_delay(Duration::from_millis(1));
+        assert_eq!(transport.send(Request::default())?, (200, "{}".into()));
+
+        let transport = Retry::new(Mock::scripted([(400, throttled)]), 2)
+            .with_base_delay(Duration::from_millis(1));
+        assert_eq!(transport.send(Request::default())?.0, 400);
+        Ok(())
+    }
+
+    #[test]
+    fn retry_returns_permanent_failures() -> Result<(), Box<dyn StdError + Send + Sync>> {
+        let not_found = r#"{"__type":"com.amazonaws.dynamodb.v20120810#ResourceNotFoundException","Message":"not found"}"#;
+        let transport = Retry::new(Mock::scripted([(400, not_found), (200, "{}")]), 3);
+        assert_eq!(transport.send(Request::default())?, (400, not_found.into()));
+        Ok(())
+    }
+
+    /// Responds only to `send_streaming`, so that decorators which do not forward it fail
+    struct Streamed(Mock);
+
+    impl Transport for Streamed {
+        fn send(
+            &self,
+            _: Request,
+        ) -> Result<(u16, String), Box<dyn StdError + Send + Sync>> {
+            Err(Box::new(StrErr("expected send_streaming".into())))
+        }
+
+        fn send_streaming(
+            &self,
+            signed: Request,
+        ) -> Result<(u16, Box<dyn Read + Send>), Box<dyn StdError + Send + Sync>> {
+            let (status, body) = self.0.send(signed)?;
+            Ok((status, Box::new(Cursor::new(body.into_bytes()))))
+        }
+    }
+
+    fn read(
+        (status, mut body): (u16, Box<dyn Read + Send>)
+    ) -> Result<(u16, String), Box<dyn StdError + Send + Sync>> {
+        let mut text = String::new();
+        body.read_to_string(&mut text)?;
+        Ok((status, text))
+    }
+
+    #[test]
+    fn decorators_forward_send_streaming() -> Result<(), Box<dyn StdError + Send + Sync>> {
+        let throttled = r#"{"__type":"com.amazonaws.dynamodb.v20120810#ProvisionedThroughputExceededException","Message":"slow down"}"#;
+        let not_found = r#"{"__type":"com.amazonaws.dynamodb.v20120810#ResourceNotFoundException","Message":"not found"}"#;
+
+        let transport = Retry::new(
+            Streamed(Mock::scripted([(400, throttled), (500, ""), (200, "{}")])),
+            3,
+        )
+        .with_base_delay(Duration::from_millis(1));
+        assert_eq!(
+            read(transport.send_streaming(Request::default())?)?,
+            (200, "{}".into())
+        );
+
+        let transport = Retry::new(Streamed(Mock::scripted([(400, not_found), (200, "{}")])), 3);
+        assert_eq!(
+            read(transport.send_streaming(Request::default())?)?,
+            (400, not_found.into())
+        );
+
+        let transport = WithTimeout::new(Streamed(Mock::new(200, "{}")), Duration::from_secs(1));
+        assert_eq!(
+            read(transport.send_streaming(Request::default())?)?,
+            (200, "{}".into())
+        );
+
+        #[cfg(feature = "log")]
+        {
+            let transport = Logging::new(Streamed(Mock::new(200, "{}")));
+            assert_eq!(
+                read(transport.send_streaming(Request::default())?)?,
+                (200, "{}".into())
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn memory_stores_items() -> Result<(), Box<dyn StdError>> {
+        let db = test_db(Memory::new("key"));
+        assert_eq!(db.get("foo")?, None);
+        db.set("foo", "bar")?;
+        assert_eq!(db.get("foo")?, Some("bar".into()));
+        assert_eq!(db.set_returning_old("foo", "baz")?, Some("bar".into()));
+        assert_eq!(db.take("foo")?, Some("baz".into()));
+        assert_eq!(db.get("foo")?, None);
+        assert!(db.get_many(["foo"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn mock_records_requests() -> Result<(), Box<dyn StdError>> {
+        let mock = Mock::new(200, "{}");
+        let db = test_db(mock.clone());
+        db.set("foo", "bar")?;
+        db.delete("foo")?;
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0].headers()["X-Amz-Target"],
+            "DynamoDB_20120810.PutItem"
+        );
+        assert_eq!(
+            requests[1].headers()["X-Amz-Target"],
+            "DynamoDB_20120810.DeleteItem"
+        );
+        let body: Value = serde_json::from_slice(requests[1].body())?;
+        assert_eq!(body["Key"]["key"]["S"], "foo");
+
+        let (status, _) = mock
+            .send_streaming(Request::default())
+            .map_err(|err| err.to_string())?;
+        assert_eq!(status, 200);
+        assert_eq!(mock.requests().len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn mock_answers_with_scripted_responses() -> Result<(), Box<dyn StdError + Send + Sync>> {
+        let mock = Mock::scripted([(500, ""), (200, "{}")]);
+        let db = test_db(mock.clone());
+        assert!(db.set("foo", "bar").is_err());
+        db.set("foo", "bar")?;
+        db.set("foo", "baz")?;
+        assert_eq!(
+            mock.bodies()
+                .iter()
+                .map(|body| body["Item"]["value"]["S"].clone())
+                .collect::<Vec<_>>(),
+            vec!["bar", "bar", "baz"]
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn decode_body_decompresses_gzip() -> Result<(), Box<dyn StdError + Send + Sync>> {
+        use std::io::Write;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"{}")?;
+        assert_eq!(decode_body(Some("gzip"), encoder.finish()?)?, "{}");
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"{}")?;
+        assert_eq!(decode_body(Some("deflate"), encoder.finish()?)?, "{}");
+        assert_eq!(decode_body(None, b"{}".to_vec())?, "{}");
+        Ok(())
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn logging_redacts_credentials() -> Result<(), Box<dyn StdError>> {
+        let request = http::Request::builder()
+            .header("Authorization", "AWS4-HMAC-SHA256 Credential=secret")
+            .header("X-Amz-Target", "DynamoDB_20120810.GetItem")
+            .body(Vec::<u8>::new())?;
+        assert_eq!(
+            redacted(request.headers()),
+            vec![
+                ("authorization", "<redacted>"),
+                ("x-amz-target", "DynamoDB_20120810.GetItem")
+            ]
+        );
+        Ok(())
+    }
+}
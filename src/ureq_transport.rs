@@ -0,0 +1,112 @@
+use crate::{Request, Transport};
+use http::{HeaderMap, HeaderValue};
+use std::{error::Error, io::Read};
+use ureq::Agent;
+
+pub struct Ureq {
+    agent: Agent,
+}
+
+impl Default for Ureq {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ureq {
+    pub fn new() -> Self {
+        Ureq {
+            agent: Agent::new(),
+        }
+    }
+}
+
+impl Transport for Ureq {
+    fn send(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, String), Box<dyn Error + Send + Sync>> {
+        self.send_full(signed)
+            .map(|(status, _, body)| (status, body))
+    }
+
+    fn send_full(
+        &self,
+        signed: Request,
+    ) -> Result<(u16, HeaderMap, String), Box<dyn Error + Send + Sync>> {
+        let mut request = self.agent.post(&signed.uri().to_string());
+        for (name, value) in signed.headers() {
+            request = request.set(name.as_str(), value.to_str()?);
+        }
+        let resp = match request.send_bytes(signed.body()) {
+            Ok(resp) => resp,
+            // error responses still carry a body describing the error
+            Err(ureq::Error::Status(_, resp)) => resp,
+            Err(err) => return Err(Box::new(err)),
+        };
+        let status = resp.status();
+        let mut headers = HeaderMap::new();
+        for name in resp.headers_names() {
+            if let Some(value) = resp.header(&name) {
+                headers.append(
+                    http::header::HeaderName::from_bytes(name.as_bytes())?,
+                    HeaderValue::from_str(value)?,
+                );
+            }
+        }
+        let mut body = Vec::new();
+        resp.into_reader().read_to_end(&mut body)?;
+        #[cfg(feature = "gzip")]
+        let body = crate::transport::decode_body(
+            headers
+                .get(http::header::CONTENT_ENCODING)
+                .and_then(|value| value.to_str().ok()),
+            body,
+        )?;
+        #[cfg(not(feature = "gzip"))]
+        let body = String::from_utf8(body)?;
+        Ok((status, headers, body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        transport::tests::server::{response, serve},
+        Credentials, Table, DB,
+    };
+
+    #[test]
+    fn round_trips_success_and_error_responses() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let (port, server) = serve(vec![
+            response("200 OK", "", br#"{"Item":{"value":{"S":"bar"}}}"#),
+            response(
+                "400 Bad Request",
+                "x-amzn-RequestId: req-1\r\n",
+                br#"{"__type":"com.amazonaws.dynamodb.v20120810#ResourceNotFoundException","Message":"not found"}"#,
+            ),
+        ])?;
+        let db = DB::new(
+            Credentials::new("test", "test"),
+            Table::new(
+                "test",
+                "key",
+                "value",
+                "us-east-1".parse()?,
+                Some(format!("http://127.0.0.1:{}", port)),
+            ),
+            Ureq::new(),
+        );
+        assert_eq!(db.get("foo")?, Some("bar".into()));
+        match db.get("foo") {
+            Err(crate::Error::Aws(err)) => {
+                assert_eq!(err.code(), "ResourceNotFoundException");
+                assert_eq!(err.request_id(), Some("req-1"));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        server.join().expect("server panicked")?;
+        Ok(())
+    }
+}